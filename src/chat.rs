@@ -6,6 +6,7 @@ use derive_builder::Builder;
 use futures_util::StreamExt;
 use reqwest::Method;
 use reqwest_eventsource::{Event, EventSource};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
@@ -25,6 +26,10 @@ pub struct ChatCompletionGeneric<C> {
     pub model: String,
     pub choices: Vec<C>,
     pub usage: Option<Usage>,
+    /// This fingerprint represents the backend configuration that the model runs with. Compare
+    /// this across requests to detect backend changes that may affect determinism, alongside the
+    /// `seed` request parameter.
+    pub system_fingerprint: Option<String>,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -32,6 +37,28 @@ pub struct ChatCompletionChoice {
     pub index: u64,
     pub finish_reason: String,
     pub message: ChatCompletionMessage,
+    pub logprobs: Option<ChatCompletionLogprobs>,
+}
+
+/// The log probabilities of the tokens generated for a [`ChatCompletionChoice`].
+#[derive(Deserialize, Clone, Debug)]
+pub struct ChatCompletionLogprobs {
+    pub content: Vec<TokenLogprob>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TokenLogprob {
+    pub token: String,
+    pub logprob: f32,
+    pub bytes: Option<Vec<u8>>,
+    pub top_logprobs: Vec<TopLogprob>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TopLogprob {
+    pub token: String,
+    pub logprob: f32,
+    pub bytes: Option<Vec<u8>>,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -47,9 +74,12 @@ pub struct ChatCompletionMessage {
     pub role: ChatCompletionMessageRole,
     /// The contents of the message
     pub content: String,
-    /// The name of the user in a multi-user chat
+    /// The name of the user in a multi-user chat, or of the function that was called
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    /// The function call generated by the model, if the model decided to call one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_call: Option<FunctionCall>,
 }
 
 /// Same as ChatCompletionMessage, but received during a response stream.
@@ -62,6 +92,10 @@ pub struct ChatCompletionMessageDelta {
     /// The name of the user in a multi-user chat
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    /// A fragment of the function call generated by the model. `arguments` is a partial JSON
+    /// string that must be accumulated across stream chunks before it can be parsed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_call: Option<FunctionCallDelta>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, Copy)]
@@ -70,6 +104,63 @@ pub enum ChatCompletionMessageRole {
     System,
     User,
     Assistant,
+    Function,
+}
+
+/// A function call generated by the model, carrying the arguments as a raw JSON string for the
+/// caller to deserialize.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct FunctionCall {
+    pub name: String,
+    pub arguments: String,
+}
+
+/// A partial [`FunctionCall`] received during a response stream. `arguments` fragments must be
+/// concatenated across chunks to recover the full JSON string.
+#[derive(Deserialize, Clone, Debug)]
+pub struct FunctionCallDelta {
+    pub name: Option<String>,
+    pub arguments: Option<String>,
+}
+
+/// A function the model may call, described as a JSON Schema of its parameters.
+#[derive(Serialize, Debug, Clone)]
+pub struct ChatCompletionFunctionDefinition {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub parameters: serde_json::Value,
+}
+
+/// Controls how the model decides whether and which function to call.
+#[derive(Serialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum ChatCompletionFunctionCall {
+    /// `"none"` or `"auto"`.
+    Mode(String),
+    /// Forces the model to call the named function.
+    Function { name: String },
+}
+
+/// Constrains the format of the model's output.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum ResponseFormat {
+    #[serde(rename = "text")]
+    Text,
+    #[serde(rename = "json_object")]
+    JsonObject,
+    #[serde(rename = "json_schema")]
+    JsonSchema { json_schema: JsonSchemaFormat },
+}
+
+/// A JSON Schema the model's output must conform to, used by [`ResponseFormat::JsonSchema`].
+#[derive(Serialize, Debug, Clone)]
+pub struct JsonSchemaFormat {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+    pub schema: serde_json::Value,
 }
 
 #[derive(Serialize, Builder, Debug, Clone)]
@@ -131,6 +222,47 @@ pub struct ChatCompletionRequest {
     #[builder(default)]
     #[serde(skip_serializing_if = "String::is_empty")]
     user: String,
+    /// A list of functions the model may generate JSON inputs for.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    functions: Vec<ChatCompletionFunctionDefinition>,
+    /// Controls how the model responds to function calls. `"none"` prevents the model from
+    /// calling a function, `"auto"` lets it decide, and naming a function forces a call to it.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    function_call: Option<ChatCompletionFunctionCall>,
+    /// Whether to return log probabilities of the output tokens.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logprobs: Option<bool>,
+    /// The number of most likely tokens to return at each position, between 0 and 20. Requires
+    /// `logprobs` to be set to `true`.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_logprobs: Option<u8>,
+    /// Constrains the model to emit valid JSON, optionally matching a JSON Schema. See
+    /// [`ChatCompletionBuilder::response_format_for`] for generating the schema variant from a
+    /// Rust type.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ResponseFormat>,
+    /// If specified, the system will make a best effort to sample deterministically, such that repeated requests with the same `seed` and parameters should return the same result. Determinism is not guaranteed; check `system_fingerprint` to monitor changes in the backend.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+    /// Options for streaming responses. Only set this when `stream` is `true`.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+}
+
+/// Options for streaming responses, set via [`ChatCompletionRequest::stream_options`].
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct StreamOptions {
+    /// If set, an additional chunk will be streamed before the `data: [DONE]` message. The
+    /// `usage` field on this chunk shows the token usage for the entire request, and the
+    /// `choices` field will always be empty.
+    pub include_usage: bool,
 }
 
 impl<C> ChatCompletionGeneric<C> {
@@ -148,6 +280,19 @@ impl ChatCompletion {
     pub async fn create(request: &ChatCompletionRequest) -> ApiResponseOrError<Self> {
         openai_post("chat/completions", request).await
     }
+
+    /// Deserializes the first choice's message content into `T`. Most useful alongside a
+    /// `response_format` built with [`ChatCompletionBuilder::response_format_for`], which
+    /// constrains the model's output to match `T`'s JSON Schema.
+    pub fn parse<T: DeserializeOwned>(&self) -> anyhow::Result<T> {
+        let content = &self
+            .choices
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("chat completion has no choices"))?
+            .message
+            .content;
+        Ok(serde_json::from_str(content)?)
+    }
 }
 
 impl ChatCompletionDelta {
@@ -171,7 +316,11 @@ async fn forward_deserialized_chat_response_stream(
     while let Some(event) = stream.next().await {
         let event = event?;
         match event {
+            Event::Message(event) if event.data == "[DONE]" => break,
             Event::Message(event) => {
+                // When `stream_options.include_usage` is set, a final chunk with populated
+                // `usage` and empty `choices` precedes `[DONE]`; it deserializes like any other
+                // chunk and is forwarded as-is.
                 let completion = serde_json::from_str::<ChatCompletionDelta>(&event.data)?;
                 tx.send(completion).await?;
             }
@@ -182,6 +331,21 @@ async fn forward_deserialized_chat_response_stream(
 }
 
 impl ChatCompletionBuilder {
+    /// Sets `response_format` to JSON-schema structured output matching `T`, generating the
+    /// schema from `T`'s [`schemars::JsonSchema`] implementation and enabling strict mode.
+    pub fn response_format_for<T: schemars::JsonSchema>(mut self) -> Self {
+        self.response_format = Some(Some(ResponseFormat::JsonSchema {
+            json_schema: JsonSchemaFormat {
+                // `schema_name` already produces an identifier-safe name (e.g. `Array_of_Foo`
+                // for `Vec<Foo>`), unlike `std::any::type_name`, which embeds `<`, `>` and `::`.
+                name: T::schema_name(),
+                strict: Some(true),
+                schema: serde_json::to_value(schemars::schema_for!(T)).unwrap(),
+            },
+        }));
+        self
+    }
+
     pub async fn create(self) -> ApiResponseOrError<ChatCompletion> {
         ChatCompletion::create(&self.build().unwrap()).await
     }
@@ -218,6 +382,7 @@ mod tests {
                 role: ChatCompletionMessageRole::User,
                 content: "Hello!".to_string(),
                 name: None,
+                function_call: None,
             }],
         )
         .temperature(0.0)
@@ -0,0 +1,227 @@
+//! Given a prompt, the model will return one or more predicted completions. This is the legacy
+//! `/v1/completions` endpoint; for conversational models, prefer [`crate::chat`].
+
+use super::{openai_post, ApiResponseOrError, Usage};
+use crate::{openai_request_stream, StreamError};
+use derive_builder::Builder;
+use futures_util::StreamExt;
+use reqwest::Method;
+use reqwest_eventsource::{Event, EventSource};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tokio::task::JoinHandle;
+
+/// A full completion.
+pub type Completion = CompletionGeneric<CompletionChoice>;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CompletionGeneric<C> {
+    pub id: String,
+    pub object: String,
+    pub created: u64,
+    pub model: String,
+    pub choices: Vec<C>,
+    pub usage: Option<Usage>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CompletionChoice {
+    pub text: String,
+    pub index: u64,
+    pub logprobs: Option<CompletionLogprobs>,
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CompletionLogprobs {
+    pub tokens: Vec<String>,
+    pub token_logprobs: Vec<Option<f32>>,
+    pub top_logprobs: Vec<Option<HashMap<String, f32>>>,
+    pub text_offset: Vec<u64>,
+}
+
+/// The prompt(s) to generate completions for.
+#[derive(Serialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum Prompt {
+    String(String),
+    Strings(Vec<String>),
+}
+
+impl From<&str> for Prompt {
+    fn from(prompt: &str) -> Self {
+        Prompt::String(prompt.to_string())
+    }
+}
+
+impl From<String> for Prompt {
+    fn from(prompt: String) -> Self {
+        Prompt::String(prompt)
+    }
+}
+
+impl From<Vec<String>> for Prompt {
+    fn from(prompts: Vec<String>) -> Self {
+        Prompt::Strings(prompts)
+    }
+}
+
+#[derive(Serialize, Builder, Debug, Clone)]
+#[builder(pattern = "owned")]
+#[builder(name = "CompletionBuilder")]
+#[builder(setter(strip_option, into))]
+pub struct CompletionRequest {
+    /// ID of the model to use.
+    model: String,
+    /// The prompt(s) to generate completions for, encoded as a string or array of strings.
+    prompt: Prompt,
+    /// The suffix that comes after a completion of inserted text.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suffix: Option<String>,
+    /// The maximum number of tokens to generate in the completion.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u64>,
+    /// What sampling temperature to use, between 0 and 2. Higher values like 0.8 will make the output more random, while lower values like 0.2 will make it more focused and deterministic.
+    ///
+    /// We generally recommend altering this or `top_p` but not both.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    /// An alternative to sampling with temperature, called nucleus sampling, where the model considers the results of the tokens with top_p probability mass. So 0.1 means only the tokens comprising the top 10% probability mass are considered.
+    ///
+    /// We generally recommend altering this or `temperature` but not both.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    /// How many completions to generate for each prompt.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    n: Option<u8>,
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<bool>,
+    /// Include the log probabilities on the `logprobs` most likely tokens, as well as the chosen tokens. For example, if `logprobs` is 5, the API will return a list of the 5 most likely tokens.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logprobs: Option<u8>,
+    /// Echo back the prompt in addition to the completion.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    echo: Option<bool>,
+    /// Up to 4 sequences where the API will stop generating further tokens.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
+    /// Number between -2.0 and 2.0. Positive values penalize new tokens based on whether they appear in the text so far, increasing the model's likelihood to talk about new topics.
+    ///
+    /// [See more information about frequency and presence penalties.](https://platform.openai.com/docs/api-reference/parameter-details)
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f32>,
+    /// Number between -2.0 and 2.0. Positive values penalize new tokens based on their existing frequency in the text so far, decreasing the model's likelihood to repeat the same line verbatim.
+    ///
+    /// [See more information about frequency and presence penalties.](https://platform.openai.com/docs/api-reference/parameter-details)
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+    /// Generates `best_of` completions server-side and returns the best one (the one with the highest log probability per token). Results cannot be streamed.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    best_of: Option<u64>,
+    /// Modify the likelihood of specified tokens appearing in the completion.
+    ///
+    /// Accepts a json object that maps tokens (specified by their token ID in the tokenizer) to an associated bias value from -100 to 100. Mathematically, the bias is added to the logits generated by the model prior to sampling. The exact effect will vary per model, but values between -1 and 1 should decrease or increase likelihood of selection; values like -100 or 100 should result in a ban or exclusive selection of the relevant token.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logit_bias: Option<HashMap<String, f32>>,
+    /// A unique identifier representing your end-user, which can help OpenAI to monitor and detect abuse. [Learn more](https://platform.openai.com/docs/guides/safety-best-practices/end-user-ids).
+    #[builder(default)]
+    #[serde(skip_serializing_if = "String::is_empty")]
+    user: String,
+}
+
+impl<C> CompletionGeneric<C> {
+    pub fn builder(model: &str, prompt: impl Into<Prompt>) -> CompletionBuilder {
+        CompletionBuilder::create_empty()
+            .model(model)
+            .prompt(prompt.into())
+    }
+}
+
+impl Completion {
+    pub async fn create(request: &CompletionRequest) -> ApiResponseOrError<Self> {
+        openai_post("completions", request).await
+    }
+
+    pub async fn create_stream(
+        request: &CompletionRequest,
+    ) -> Result<(Receiver<Self>, JoinHandle<anyhow::Result<()>>), StreamError> {
+        let stream =
+            openai_request_stream(Method::POST, "completions", |r| r.json(request)).await?;
+        let (tx, rx) = channel::<Self>(32);
+        Ok((
+            rx,
+            tokio::spawn(forward_deserialized_completion_response_stream(stream, tx)),
+        ))
+    }
+}
+
+async fn forward_deserialized_completion_response_stream(
+    mut stream: EventSource,
+    tx: Sender<Completion>,
+) -> anyhow::Result<()> {
+    while let Some(event) = stream.next().await {
+        let event = event?;
+        match event {
+            Event::Message(event) if event.data == "[DONE]" => break,
+            Event::Message(event) => {
+                let completion = serde_json::from_str::<Completion>(&event.data)?;
+                tx.send(completion).await?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+impl CompletionBuilder {
+    pub async fn create(self) -> ApiResponseOrError<Completion> {
+        Completion::create(&self.build().unwrap()).await
+    }
+
+    pub async fn create_stream(
+        mut self,
+    ) -> Result<(Receiver<Completion>, JoinHandle<anyhow::Result<()>>), StreamError> {
+        self.stream = Some(Some(true));
+        Completion::create_stream(&self.build().unwrap()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::set_key;
+    use dotenvy::dotenv;
+    use std::env;
+
+    #[tokio::test]
+    async fn completion() {
+        dotenv().ok();
+        set_key(env::var("OPENAI_KEY").unwrap());
+
+        let completion = Completion::builder("gpt-3.5-turbo-instruct", "Hello!")
+            .temperature(0.0)
+            .create()
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            completion.choices.first().unwrap().text,
+            " Hello there! How can I assist you today?"
+        );
+    }
+}